@@ -0,0 +1,124 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! A buffered output subsystem modeled on termcolor's `Buffer`/`BufferWriter`.
+//!
+//! Each worker writes its styled output into its own [`Buffer`] instead of
+//! writing directly to stdout/stderr. A [`BufferWriter`] then flushes those
+//! buffers one at a time, so output from concurrent workers is never
+//! interleaved. Buffers honor
+//! [`use_color_for`](crate::colors::use_color_for) and
+//! [`color_level_for`](crate::colors::color_level_for) for the stream
+//! they're headed to, so the bytes a worker produces are already
+//! down-converted for that stream's terminal.
+
+use std::io;
+
+use termcolor::Buffer as TcBuffer;
+use termcolor::BufferWriter as TcBufferWriter;
+use termcolor::ColorChoice;
+use termcolor::ColorSpec;
+use termcolor::WriteColor;
+
+use crate::colors::color_level_for;
+use crate::colors::downgrade_color_spec;
+use crate::colors::use_color_for;
+use crate::colors::ColorLevel;
+use crate::Stream;
+
+fn color_choice(stream: Stream) -> ColorChoice {
+  if use_color_for(stream) {
+    ColorChoice::Always
+  } else {
+    ColorChoice::Never
+  }
+}
+
+/// An in-memory buffer that colored output can be written to. Obtained from
+/// a [`BufferWriter`] and printed back through it once filled.
+pub struct Buffer {
+  inner: TcBuffer,
+  level: ColorLevel,
+}
+
+impl Buffer {
+  /// Returns the contents of this buffer as raw bytes.
+  pub fn as_slice(&self) -> &[u8] {
+    self.inner.as_slice()
+  }
+
+  /// Returns true if no bytes have been written to this buffer.
+  pub fn is_empty(&self) -> bool {
+    self.inner.is_empty()
+  }
+
+  /// Clears this buffer so it can be reused.
+  pub fn clear(&mut self) {
+    self.inner.clear()
+  }
+
+  /// Sets the color that subsequent writes should use, down-converting it
+  /// to whatever this buffer's stream supports.
+  pub fn set_color(&mut self, spec: &ColorSpec) -> io::Result<()> {
+    let spec = downgrade_color_spec(spec, self.level);
+    self.inner.set_color(&spec)
+  }
+
+  /// Resets the color of this buffer to the terminal defaults.
+  pub fn reset(&mut self) -> io::Result<()> {
+    self.inner.reset()
+  }
+}
+
+impl io::Write for Buffer {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    self.inner.write(buf)
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    self.inner.flush()
+  }
+}
+
+/// Creates [`Buffer`]s and flushes them to a standard stream one at a time,
+/// so buffers written from multiple threads never have their output
+/// interleaved.
+pub struct BufferWriter {
+  inner: TcBufferWriter,
+  stream: Stream,
+}
+
+impl BufferWriter {
+  /// Creates a new `BufferWriter` that flushes buffers to the given stream.
+  pub fn new(stream: Stream) -> Self {
+    let inner = match stream {
+      Stream::Stdout => TcBufferWriter::stdout(color_choice(stream)),
+      Stream::Stderr => TcBufferWriter::stderr(color_choice(stream)),
+    };
+    Self { inner, stream }
+  }
+
+  /// Creates a new `BufferWriter` that flushes buffers to stdout.
+  pub fn stdout() -> Self {
+    Self::new(Stream::Stdout)
+  }
+
+  /// Creates a new `BufferWriter` that flushes buffers to stderr.
+  pub fn stderr() -> Self {
+    Self::new(Stream::Stderr)
+  }
+
+  /// Creates a new, empty buffer.
+  pub fn buffer(&self) -> Buffer {
+    Buffer {
+      inner: self.inner.buffer(),
+      level: color_level_for(self.stream),
+    }
+  }
+
+  /// Flushes the given buffer's contents to this writer's stream. Holds
+  /// the writer's internal lock for the duration of the write so that
+  /// concurrent calls from other threads never interleave.
+  pub fn print(&self, buffer: &Buffer) -> io::Result<()> {
+    self.inner.print(&buffer.inner)
+  }
+}
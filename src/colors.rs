@@ -1,9 +1,12 @@
 // Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
 
 use once_cell::sync::Lazy;
+use regex::Regex;
+use std::borrow::Cow;
 use std::fmt;
 use std::fmt::Write as _;
 use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU8;
 use termcolor::Ansi;
 use termcolor::Color::Ansi256;
 use termcolor::Color::Black;
@@ -14,17 +17,40 @@ use termcolor::Color::Magenta;
 use termcolor::Color::Red;
 use termcolor::Color::White;
 use termcolor::Color::Yellow;
-use termcolor::ColorSpec;
 use termcolor::WriteColor;
 
+// Re-exported so callers that only need to build a `ColorSpec`/`Color` for
+// this crate's APIs (`StyleBuilder`, `Buffer::set_color`,
+// `downgrade_color_spec`, ...) don't have to add `termcolor` as a direct,
+// version-matched dependency themselves.
+pub use termcolor::Color;
+pub use termcolor::ColorSpec;
+
+use crate::Stream;
+
 #[cfg(windows)]
 use termcolor::BufferWriter;
 #[cfg(windows)]
 use termcolor::ColorChoice;
 
 static FORCE_COLOR: Lazy<bool> = Lazy::new(|| {
-  std::env::var_os("FORCE_COLOR")
+  let force_color = std::env::var_os("FORCE_COLOR")
     .map(|v| !v.is_empty())
+    .unwrap_or(false);
+  // `CLICOLOR_FORCE` is the same convention under a different name, used
+  // by e.g. git and ripgrep; honor it like `FORCE_COLOR`, except that a
+  // value of `0` doesn't force color.
+  let clicolor_force = std::env::var_os("CLICOLOR_FORCE")
+    .map(|v| v != "0" && !v.is_empty())
+    .unwrap_or(false);
+  force_color || clicolor_force
+});
+
+// `CLICOLOR=0` disables color, but only for streams that aren't a TTY
+// themselves (a TTY stream is assumed to want color regardless).
+static CLICOLOR_DISABLED: Lazy<bool> = Lazy::new(|| {
+  std::env::var_os("CLICOLOR")
+    .map(|v| v == "0")
     .unwrap_or(false)
 });
 
@@ -137,8 +163,47 @@ static COLOR_LEVEL: Lazy<ColorLevel> = Lazy::new(|| {
   }
 });
 
+// Sentinel-encoded `Option<ColorLevel>` override for `get_color_level`,
+// set via `set_color_level` (and implicitly by `set_use_color`) so a
+// runtime override isn't stuck behind the one-shot `COLOR_LEVEL` `Lazy`.
+// 0 means "no override"; 1..=4 map to the four `ColorLevel` variants.
+static COLOR_LEVEL_OVERRIDE: Lazy<AtomicU8> = Lazy::new(|| AtomicU8::new(0));
+
+fn encode_color_level(level: ColorLevel) -> u8 {
+  match level {
+    ColorLevel::None => 1,
+    ColorLevel::Ansi => 2,
+    ColorLevel::Ansi256 => 3,
+    ColorLevel::TrueColor => 4,
+  }
+}
+
+fn decode_color_level(value: u8) -> Option<ColorLevel> {
+  match value {
+    1 => Some(ColorLevel::None),
+    2 => Some(ColorLevel::Ansi),
+    3 => Some(ColorLevel::Ansi256),
+    4 => Some(ColorLevel::TrueColor),
+    _ => None,
+  }
+}
+
 pub fn get_color_level() -> ColorLevel {
-  *COLOR_LEVEL
+  let overridden =
+    COLOR_LEVEL_OVERRIDE.load(std::sync::atomic::Ordering::Relaxed);
+  decode_color_level(overridden).unwrap_or(*COLOR_LEVEL)
+}
+
+/// Overrides the detected `ColorLevel`.
+///
+/// Useful alongside `set_use_color` to force a capability back on after it
+/// was detected as unsupported (e.g. on Wasm, where it's always `None` by
+/// default).
+pub fn set_color_level(level: ColorLevel) {
+  COLOR_LEVEL_OVERRIDE.store(
+    encode_color_level(level),
+    std::sync::atomic::Ordering::Relaxed,
+  );
 }
 
 /// Gets whether color should be used in the output.
@@ -161,8 +226,71 @@ pub fn force_color() -> bool {
 /// Sets whether color should be used in the output.
 ///
 /// This overrides the default values set via the `FORCE_COLOR` and `NO_COLOR` env vars.
+///
+/// When enabling color, this also pulls the current [`get_color_level`]
+/// out of `None` (e.g. because this is Wasm, where the level is always
+/// `None` by default) so it doesn't keep downgrading every style away to
+/// nothing. It leaves an already-correct level (whether detected, like
+/// `Ansi256` on a 256-color terminal, or set explicitly via
+/// `set_color_level`) alone rather than forcing `TrueColor`, and doesn't
+/// touch the level at all when disabling color, since `use_color()` is
+/// checked before `get_color_level()` everywhere it matters. Call
+/// `set_color_level` afterwards for finer control.
 pub fn set_use_color(use_color: bool) {
   USE_COLOR.store(use_color, std::sync::atomic::Ordering::Relaxed);
+  if use_color && matches!(get_color_level(), ColorLevel::None) {
+    set_color_level(ColorLevel::TrueColor);
+  }
+}
+
+/// Like [`use_color`], but makes the decision for a specific stream: a
+/// piped stdout and a TTY stderr can legitimately want different answers,
+/// which the global `use_color()` can't express.
+///
+/// Color is never used while [`use_color`] is false (e.g. after
+/// `set_use_color(false)`). Otherwise, `FORCE_COLOR` and `CLICOLOR_FORCE`
+/// (folded into `FORCE_COLOR` above) force color on, and `CLICOLOR=0`
+/// disables color for this stream unless it's
+/// actually connected to a TTY; beyond that, the stream must both pass
+/// [`use_color`] and be a TTY to get color.
+pub fn use_color_for(stream: Stream) -> bool {
+  resolve_use_color_for(
+    use_color(),
+    *FORCE_COLOR,
+    *CLICOLOR_DISABLED,
+    stream.is_tty(),
+  )
+}
+
+/// The actual precedence logic behind [`use_color_for`], pulled out as a
+/// pure function of its inputs so it can be unit tested without having to
+/// fake out env vars or a real TTY.
+fn resolve_use_color_for(
+  global_use_color: bool,
+  force_color: bool,
+  clicolor_disabled: bool,
+  is_tty: bool,
+) -> bool {
+  if !global_use_color {
+    return false;
+  }
+  if force_color {
+    return true;
+  }
+  if clicolor_disabled && !is_tty {
+    return false;
+  }
+  is_tty
+}
+
+/// Like [`get_color_level`], but scoped to a specific stream via
+/// [`use_color_for`].
+pub fn color_level_for(stream: Stream) -> ColorLevel {
+  if !use_color_for(stream) {
+    ColorLevel::None
+  } else {
+    get_color_level()
+  }
 }
 
 /// Enables ANSI color output on Windows. This is a no-op on other platforms.
@@ -210,7 +338,7 @@ impl fmt::Write for StdIoStdFmtWriter<'_> {
   }
 }
 
-pub struct Style<I: fmt::Display> {
+pub struct Style<I = ()> {
   colorspec: ColorSpec,
   inner: I,
 }
@@ -220,16 +348,189 @@ impl<I: fmt::Display> fmt::Display for Style<I> {
     if !use_color() {
       return fmt::Display::fmt(&self.inner, f);
     }
+    let colorspec = downgrade_color_spec(&self.colorspec, get_color_level());
     let mut ansi_writer = Ansi::new(StdFmtStdIoWriter(f));
-    ansi_writer
-      .set_color(&self.colorspec)
-      .map_err(|_| fmt::Error)?;
+    ansi_writer.set_color(&colorspec).map_err(|_| fmt::Error)?;
     write!(StdIoStdFmtWriter(&mut ansi_writer), "{}", self.inner)?;
     ansi_writer.reset().map_err(|_| fmt::Error)?;
     Ok(())
   }
 }
 
+/// Down-converts every color in a `ColorSpec` so it fits within the given
+/// `ColorLevel`, leaving other attributes (bold, underline, etc.) untouched.
+///
+/// `ColorLevel::None` means the terminal doesn't support ANSI escapes at
+/// all, not just colors, so it clears the whole spec rather than just the
+/// colors.
+///
+/// This is what lets callers build styles with truecolor or 256-color values
+/// without worrying about whether the current terminal actually supports
+/// them: `Style::fmt` always runs the spec through this before writing it.
+pub fn downgrade_color_spec(spec: &ColorSpec, level: ColorLevel) -> ColorSpec {
+  if matches!(level, ColorLevel::None) {
+    return ColorSpec::new();
+  }
+  let mut out = spec.clone();
+  let intense_hint = spec.bold() || spec.intense();
+  if let Some(fg) = spec.fg().copied() {
+    out.set_fg(downgrade_color(fg, level, intense_hint));
+  }
+  if let Some(bg) = spec.bg().copied() {
+    out.set_bg(downgrade_color(bg, level, intense_hint));
+  }
+  out
+}
+
+fn downgrade_color(
+  color: Color,
+  level: ColorLevel,
+  intense_hint: bool,
+) -> Option<Color> {
+  match level {
+    ColorLevel::None => None,
+    ColorLevel::TrueColor => Some(color),
+    ColorLevel::Ansi256 => match color {
+      Color::Rgb(r, g, b) => Some(Ansi256(rgb_to_ansi256(r, g, b))),
+      other => Some(other),
+    },
+    ColorLevel::Ansi => match color {
+      Color::Rgb(r, g, b) => Some(nearest_ansi16(r, g, b, intense_hint)),
+      Ansi256(index) => {
+        let (r, g, b) = ansi256_to_rgb(index);
+        Some(nearest_ansi16(r, g, b, intense_hint))
+      }
+      other => Some(other),
+    },
+  }
+}
+
+/// The 16 base ANSI colors' approximate RGB values, in their "normal"
+/// and "bright" (intense) variants, used to find the nearest 16-color
+/// match for a truecolor or 256-color value.
+const ANSI16_NORMAL: [(Color, (u8, u8, u8)); 8] = [
+  (Black, (0, 0, 0)),
+  (Red, (205, 0, 0)),
+  (Green, (0, 205, 0)),
+  (Yellow, (205, 205, 0)),
+  (Blue, (0, 0, 238)),
+  (Magenta, (205, 0, 205)),
+  (Cyan, (0, 205, 205)),
+  (White, (229, 229, 229)),
+];
+
+const ANSI16_BRIGHT: [(Color, (u8, u8, u8)); 8] = [
+  (Black, (127, 127, 127)),
+  (Red, (255, 0, 0)),
+  (Green, (0, 255, 0)),
+  (Yellow, (255, 255, 0)),
+  (Blue, (92, 92, 255)),
+  (Magenta, (255, 0, 255)),
+  (Cyan, (0, 255, 255)),
+  (White, (255, 255, 255)),
+];
+
+fn squared_distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+  let dr = a.0 as i32 - b.0 as i32;
+  let dg = a.1 as i32 - b.1 as i32;
+  let db = a.2 as i32 - b.2 as i32;
+  (dr * dr + dg * dg + db * db) as u32
+}
+
+/// Finds the closest match (and its squared distance) to `target` within
+/// a single 8-color ANSI table.
+fn closest_in_table(
+  table: &[(Color, (u8, u8, u8)); 8],
+  target: (u8, u8, u8),
+) -> (Color, u32) {
+  let mut best_color = Black;
+  let mut best_distance = u32::MAX;
+  for (color, rgb) in table {
+    let distance = squared_distance(*rgb, target);
+    if distance < best_distance {
+      best_distance = distance;
+      best_color = *color;
+    }
+  }
+  (best_color, best_distance)
+}
+
+/// Finds the nearest of the 16 base ANSI colors to the given RGB value.
+/// When `intense_hint` is set (the source color was bold/intense), a tie
+/// between the closest normal entry and the closest bright entry goes to
+/// the bright one; otherwise it goes to the normal one.
+fn nearest_ansi16(r: u8, g: u8, b: u8, intense_hint: bool) -> Color {
+  let target = (r, g, b);
+  let (preferred, other) = if intense_hint {
+    (&ANSI16_BRIGHT, &ANSI16_NORMAL)
+  } else {
+    (&ANSI16_NORMAL, &ANSI16_BRIGHT)
+  };
+  let (preferred_color, preferred_distance) =
+    closest_in_table(preferred, target);
+  let (other_color, other_distance) = closest_in_table(other, target);
+  if other_distance < preferred_distance {
+    other_color
+  } else {
+    preferred_color
+  }
+}
+
+/// Maps an xterm 256-color index (0–255) to its approximate RGB value,
+/// used when down-converting an `Ansi256` color to the 16-color palette.
+fn ansi256_to_rgb(index: u8) -> (u8, u8, u8) {
+  const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+  match index {
+    0..=7 => ANSI16_NORMAL[index as usize].1,
+    8..=15 => ANSI16_BRIGHT[(index - 8) as usize].1,
+    16..=231 => {
+      let i = index - 16;
+      let r = LEVELS[(i / 36) as usize];
+      let g = LEVELS[(i / 6 % 6) as usize];
+      let b = LEVELS[(i % 6) as usize];
+      (r, g, b)
+    }
+    232..=255 => {
+      let v = 8 + 10 * (index - 232);
+      (v, v, v)
+    }
+  }
+}
+
+/// Maps an RGB value to the nearest xterm 256-color palette index, checking
+/// both the 6x6x6 color cube (indices 16–231) and the grayscale ramp
+/// (indices 232–255) and picking whichever is closer.
+fn rgb_to_ansi256(r: u8, g: u8, b: u8) -> u8 {
+  const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+  let nearest_level_index = |c: u8| -> usize {
+    LEVELS
+      .iter()
+      .enumerate()
+      .min_by_key(|(_, &level)| (level as i32 - c as i32).abs())
+      .map(|(i, _)| i)
+      .unwrap()
+  };
+  let ri = nearest_level_index(r);
+  let gi = nearest_level_index(g);
+  let bi = nearest_level_index(b);
+  let cube_index = 16 + 36 * ri + 6 * gi + bi;
+  let cube_rgb = (LEVELS[ri], LEVELS[gi], LEVELS[bi]);
+  let cube_distance = squared_distance(cube_rgb, (r, g, b));
+
+  let gray = ((r as u32 + g as u32 + b as u32) / 3) as i32;
+  let gray_n = (((gray - 8) as f64 / 10.0).round().clamp(0.0, 23.0)) as u8;
+  let gray_index = 232 + gray_n;
+  let gray_value = 8 + 10 * gray_n;
+  let gray_distance =
+    squared_distance((gray_value, gray_value, gray_value), (r, g, b));
+
+  if gray_distance < cube_distance {
+    gray_index
+  } else {
+    cube_index as u8
+  }
+}
+
 #[inline]
 fn style<'a, S: fmt::Display + 'a>(s: S, colorspec: ColorSpec) -> Style<S> {
   Style {
@@ -238,6 +539,102 @@ fn style<'a, S: fmt::Display + 'a>(s: S, colorspec: ColorSpec) -> Style<S> {
   }
 }
 
+impl Style {
+  /// Starts building a [`StyleBuilder`] with an arbitrary combination of
+  /// colors (including 24-bit RGB) and attributes, for cases where the
+  /// fixed palette of functions below (`red_bold`, `cyan_with_underline`,
+  /// ...) doesn't cover what's needed.
+  pub fn builder() -> StyleBuilder {
+    StyleBuilder::new()
+  }
+}
+
+/// A chainable builder for [`Style`], supporting truecolor RGB foreground
+/// and background colors in addition to the named `Color` variants.
+///
+/// ```no_run
+/// use deno_terminal::colors::Style;
+/// use deno_terminal::colors::Color;
+///
+/// let styled = Style::builder()
+///   .fg_rgb(0xff, 0xa5, 0x00)
+///   .bg(Color::Black)
+///   .bold()
+///   .underline()
+///   .wrap("warning");
+/// println!("{styled}");
+/// ```
+pub struct StyleBuilder {
+  colorspec: ColorSpec,
+}
+
+impl StyleBuilder {
+  fn new() -> Self {
+    Self {
+      colorspec: ColorSpec::new(),
+    }
+  }
+
+  /// Sets the foreground color.
+  pub fn fg(mut self, color: Color) -> Self {
+    self.colorspec.set_fg(Some(color));
+    self
+  }
+
+  /// Sets the foreground color to a 24-bit RGB value.
+  pub fn fg_rgb(self, r: u8, g: u8, b: u8) -> Self {
+    self.fg(Color::Rgb(r, g, b))
+  }
+
+  /// Sets the background color.
+  pub fn bg(mut self, color: Color) -> Self {
+    self.colorspec.set_bg(Some(color));
+    self
+  }
+
+  /// Sets the background color to a 24-bit RGB value.
+  pub fn bg_rgb(self, r: u8, g: u8, b: u8) -> Self {
+    self.bg(Color::Rgb(r, g, b))
+  }
+
+  /// Makes the text bold.
+  pub fn bold(mut self) -> Self {
+    self.colorspec.set_bold(true);
+    self
+  }
+
+  /// Underlines the text.
+  pub fn underline(mut self) -> Self {
+    self.colorspec.set_underline(true);
+    self
+  }
+
+  /// Italicizes the text.
+  pub fn italic(mut self) -> Self {
+    self.colorspec.set_italic(true);
+    self
+  }
+
+  /// Dims the text.
+  pub fn dimmed(mut self) -> Self {
+    self.colorspec.set_dimmed(true);
+    self
+  }
+
+  /// Marks the color as intense (bright).
+  pub fn intense(mut self) -> Self {
+    self.colorspec.set_intense(true);
+    self
+  }
+
+  /// Finishes the builder, wrapping `inner` so it renders with the
+  /// accumulated colorspec (still subject to the usual `ColorLevel`
+  /// down-conversion and `NO_COLOR`/`FORCE_COLOR` handling).
+  pub fn wrap<S: fmt::Display>(self, inner: S) -> Style<S> {
+    style(inner, self.colorspec)
+  }
+}
+
 pub fn red_bold<'a, S: fmt::Display + 'a>(s: S) -> Style<S> {
   let mut style_spec = ColorSpec::new();
   style_spec.set_fg(Some(Red)).set_bold(true);
@@ -364,3 +761,186 @@ pub fn white_bold_on_red<'a>(
     .set_fg(Some(White));
   style(s, style_spec)
 }
+
+static STRIP_ANSI_RE: Lazy<Regex> = Lazy::new(|| {
+  Regex::new(
+    "[\x1b\u{9b}][\\[()#;?]*(?:[0-9]{1,4}(?:;[0-9]{0,4})*)?[0-9A-PRZcf-nqry=><]",
+  )
+  .unwrap()
+});
+
+/// Strips ANSI escape codes (color, cursor movement, etc.) from a string.
+pub fn strip_ansi_codes(s: &str) -> Cow<'_, str> {
+  STRIP_ANSI_RE.replace_all(s, "")
+}
+
+/// Measures the display width of `s` as it would appear in a terminal,
+/// ignoring any ANSI escape codes it contains.
+pub fn measured_width(s: &str) -> usize {
+  unicode_width::UnicodeWidthStr::width(&*strip_ansi_codes(s))
+}
+
+const ELLIPSIS: &str = "...";
+
+/// Truncates `s` to fit within `max_width` display columns, appending an
+/// ellipsis if it had to cut anything off. ANSI escape sequences are never
+/// split and are copied through untouched; wide graphemes are never split
+/// either, so the result may be one column narrower than `max_width`.
+pub fn truncate_with_ellipsis(s: &str, max_width: usize) -> Cow<'_, str> {
+  if measured_width(s) <= max_width {
+    return Cow::Borrowed(s);
+  }
+
+  let ellipsis_width = unicode_width::UnicodeWidthStr::width(ELLIPSIS);
+  if max_width <= ellipsis_width {
+    return Cow::Owned(ELLIPSIS.chars().take(max_width).collect());
+  }
+  let target_width = max_width - ellipsis_width;
+
+  let mut result = String::new();
+  let mut width_used = 0;
+  let mut pos = 0;
+  while pos < s.len() {
+    if let Some(m) = STRIP_ANSI_RE.find(&s[pos..]) {
+      if m.start() == 0 {
+        result.push_str(m.as_str());
+        pos += m.end();
+        continue;
+      }
+    }
+    let c = s[pos..].chars().next().unwrap();
+    let char_width = unicode_width::UnicodeWidthChar::width(c).unwrap_or(0);
+    if width_used + char_width > target_width {
+      break;
+    }
+    result.push(c);
+    width_used += char_width;
+    pos += c.len_utf8();
+  }
+  // If we cut the string off mid-style, any escape sequences past the cut
+  // point (including a closing reset) never get copied over. Reset
+  // unconditionally whenever the source had any styling, so a dropped
+  // color/attribute can't leak into whatever gets printed after the
+  // truncated string.
+  if STRIP_ANSI_RE.is_match(s) {
+    result.push_str("\x1b[0m");
+  }
+  result.push_str(ELLIPSIS);
+  Cow::Owned(result)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn downgrade_color_spec_clears_everything_at_none() {
+    let mut spec = ColorSpec::new();
+    spec.set_fg(Some(Red)).set_bold(true).set_underline(true);
+    let downgraded = downgrade_color_spec(&spec, ColorLevel::None);
+    assert_eq!(downgraded.fg(), None);
+    assert_eq!(downgraded.bg(), None);
+    assert!(!downgraded.bold());
+    assert!(!downgraded.underline());
+  }
+
+  #[test]
+  fn downgrade_color_spec_keeps_attributes_above_none() {
+    let mut spec = ColorSpec::new();
+    spec.set_fg(Some(Color::Rgb(255, 0, 0))).set_bold(true);
+    let downgraded = downgrade_color_spec(&spec, ColorLevel::Ansi);
+    assert!(downgraded.bold());
+    assert_eq!(downgraded.fg(), Some(&Red));
+  }
+
+  #[test]
+  fn rgb_to_ansi256_maps_cube_corners() {
+    // Pure black and pure white both sit exactly on the 6x6x6 cube.
+    assert_eq!(rgb_to_ansi256(0, 0, 0), 16);
+    assert_eq!(rgb_to_ansi256(255, 255, 255), 231);
+  }
+
+  #[test]
+  fn ansi256_to_rgb_round_trips_cube_indices() {
+    for index in 16..=231u8 {
+      let (r, g, b) = ansi256_to_rgb(index);
+      assert_eq!(rgb_to_ansi256(r, g, b), index);
+    }
+  }
+
+  #[test]
+  fn nearest_ansi16_picks_exact_matches() {
+    assert_eq!(nearest_ansi16(255, 0, 0, false), Red);
+    assert_eq!(nearest_ansi16(0, 255, 0, false), Green);
+  }
+
+  #[test]
+  fn nearest_ansi16_prefers_bright_on_tie_when_intense() {
+    // (178, 178, 178) is equidistant (squared distance 7803) from normal
+    // white (229, 229, 229) and bright black (127, 127, 127).
+    assert_eq!(nearest_ansi16(178, 178, 178, true), Black);
+    assert_eq!(nearest_ansi16(178, 178, 178, false), White);
+  }
+
+  #[test]
+  fn strip_ansi_codes_removes_escapes() {
+    assert_eq!(strip_ansi_codes("\x1b[31mred\x1b[0m"), "red");
+  }
+
+  #[test]
+  fn measured_width_ignores_escapes() {
+    assert_eq!(measured_width("\x1b[31mred\x1b[0m"), 3);
+  }
+
+  #[test]
+  fn truncate_with_ellipsis_leaves_short_strings_alone() {
+    assert_eq!(truncate_with_ellipsis("short", 10), "short");
+  }
+
+  #[test]
+  fn truncate_with_ellipsis_cuts_and_appends_ellipsis() {
+    assert_eq!(truncate_with_ellipsis("abcdefgh", 5), "ab...");
+  }
+
+  #[test]
+  fn truncate_with_ellipsis_never_splits_a_wide_grapheme() {
+    // The wide '世' (width 2) would overflow the 2-column budget left after
+    // the ellipsis, so it's dropped whole rather than split.
+    assert_eq!(truncate_with_ellipsis("ab世defgh", 5), "ab...");
+  }
+
+  #[test]
+  fn truncate_with_ellipsis_resets_dropped_trailing_style() {
+    let truncated =
+      truncate_with_ellipsis("\x1b[31mabcdefgh\x1b[0m", 5);
+    assert!(truncated.ends_with("\x1b[0m..."));
+  }
+
+  #[test]
+  fn use_color_for_precedence() {
+    // (global_use_color, force_color, clicolor_disabled, is_tty) -> expected
+    let cases = [
+      (false, false, false, true, false),
+      (true, true, false, false, true),
+      (true, false, true, false, false),
+      (true, false, true, true, true),
+      (true, false, false, false, false),
+      (true, false, false, true, true),
+    ];
+    for (global_use_color, force_color, clicolor_disabled, is_tty, expected) in
+      cases
+    {
+      assert_eq!(
+        resolve_use_color_for(
+          global_use_color,
+          force_color,
+          clicolor_disabled,
+          is_tty
+        ),
+        expected,
+        "global_use_color={global_use_color} force_color={force_color} \
+         clicolor_disabled={clicolor_disabled} is_tty={is_tty}"
+      );
+    }
+  }
+}
@@ -6,6 +6,8 @@ use once_cell::sync::Lazy;
 
 #[cfg(feature = "colors")]
 pub mod colors;
+#[cfg(feature = "colors")]
+pub mod buffer;
 
 static IS_STDOUT_TTY: Lazy<bool> =
   Lazy::new(|| std::io::stdout().is_terminal());
@@ -19,3 +21,21 @@ pub fn is_stdout_tty() -> bool {
 pub fn is_stderr_tty() -> bool {
   *IS_STDERR_TTY
 }
+
+/// A standard output stream, used by APIs that need to make a decision
+/// (such as whether to colorize output) per-stream rather than globally.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stream {
+  Stdout,
+  Stderr,
+}
+
+impl Stream {
+  /// Whether this stream is connected to a TTY.
+  pub fn is_tty(self) -> bool {
+    match self {
+      Stream::Stdout => is_stdout_tty(),
+      Stream::Stderr => is_stderr_tty(),
+    }
+  }
+}